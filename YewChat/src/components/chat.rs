@@ -1,20 +1,168 @@
+use std::collections::HashMap;
+
+use gloo_timers::callback::Timeout;
 use serde::{Deserialize, Serialize};
-use web_sys::HtmlInputElement;
+use wasm_bindgen::JsCast;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement, PointerEvent};
 use yew::prelude::*;
 use yew_agent::{Bridge, Bridged};
 
 use crate::services::event_bus::EventBus;
+use crate::services::websocket::ConnectionStatus;
 use crate::{services::websocket::WebsocketService, User};
 
+/// How long a `Typing` frame stays debounced before another keystroke
+/// is allowed to send a fresh one.
+const TYPING_THROTTLE_MS: u32 = 2_000;
+/// How long to wait for another keystroke before announcing the user
+/// has stopped typing.
+const TYPING_CLEAR_MS: u32 = 3_000;
+/// How long a received `Typing` status is trusted before it's reverted to
+/// `Online` locally. Covers the typist's tab closing, crashing, or losing
+/// its connection before it ever gets to send its own `Presence` frame
+/// with `PresenceStatus::Online`; comfortably longer than `TYPING_CLEAR_MS` so it
+/// doesn't race a well-behaved sender's own cooperative clear.
+const TYPING_RECEIVER_EXPIRE_MS: u32 = 5_000;
+
 pub enum Msg {
     HandleMsg(String),
     SubmitMessage,
+    InputChanged,
+    TypingThrottleElapsed,
+    StopTyping,
+    TypingExpired(String),
+    PointerDown(f64, f64),
+    PointerMove(f64, f64),
+    PointerUp,
+    ClearBoard,
+    ConnectionStatus(ConnectionStatus),
+    OpenRoom(Room),
+}
+
+/// A conversation scope: the public room everyone shares, or a private
+/// one-on-one conversation with the named user.
+#[derive(Clone, PartialEq, Eq)]
+pub enum Room {
+    Public,
+    Direct(String),
 }
 
 #[derive(Deserialize)]
 struct MessageData {
     from: String,
     message: String,
+    /// Recipient of a direct message; `None` for public room messages.
+    #[serde(default)]
+    to: Option<String>,
+    /// Epoch-millis the server stamped this message with. Older servers
+    /// that don't send it fall back to arrival order and the local clock.
+    #[serde(default)]
+    sent_at: Option<f64>,
+}
+
+impl MessageData {
+    /// True if the viewer's own client authored this message. The server
+    /// rewrites `from` to the literal `"You"` on the frame it echoes back
+    /// to the sender, for every message type (public or direct) — this is
+    /// the one place that convention is decoded, so filtering and labeling
+    /// never disagree about whose message it is.
+    fn is_mine(&self) -> bool {
+        self.from == "You"
+    }
+}
+
+/// Renders a short, human relative label ("just now", "3m", "2h", or a
+/// clock time for anything older than a day).
+fn relative_time(sent_at_ms: f64) -> String {
+    let diff_secs = ((js_sys::Date::now() - sent_at_ms) / 1000.0).max(0.0);
+    if diff_secs < 45.0 {
+        "just now".to_string()
+    } else if diff_secs < 3600.0 {
+        format!("{}m", (diff_secs / 60.0).round().max(1.0) as i64)
+    } else if diff_secs < 86_400.0 {
+        format!("{}h", (diff_secs / 3600.0).round().max(1.0) as i64)
+    } else {
+        let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(sent_at_ms));
+        format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+    }
+}
+
+/// Groups a timestamp into "Today", "Yesterday", or an absolute date.
+fn day_label(sent_at_ms: f64) -> String {
+    let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(sent_at_ms));
+    let today = js_sys::Date::new_0();
+    let is_same_day = |a: &js_sys::Date, b: &js_sys::Date| {
+        a.get_full_year() == b.get_full_year()
+            && a.get_month() == b.get_month()
+            && a.get_date() == b.get_date()
+    };
+    if is_same_day(&date, &today) {
+        return "Today".to_string();
+    }
+    let yesterday = js_sys::Date::new_0();
+    yesterday.set_date(yesterday.get_date() - 1);
+    if is_same_day(&date, &yesterday) {
+        return "Yesterday".to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02}",
+        date.get_full_year(),
+        date.get_month() + 1,
+        date.get_date()
+    )
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum PresenceStatus {
+    Online,
+    Away,
+    Typing,
+}
+
+#[derive(Deserialize, Serialize)]
+struct PresenceData {
+    from: String,
+    status: PresenceStatus,
+}
+
+#[derive(Deserialize, Serialize)]
+struct TypingData {
+    from: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+struct Stroke {
+    color: String,
+    width: f64,
+    points: Vec<(f64, f64)>,
+}
+
+/// Renders a message body as sanitized HTML.
+///
+/// The raw text is parsed as Markdown, then run through an allowlist
+/// sanitizer so a malicious peer can't smuggle `<script>`/event handlers
+/// into another user's page via `Html::from_html_unchecked`.
+fn render_message_html(raw: &str) -> Html {
+    use pulldown_cmark::{html, Options, Parser};
+
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TABLES);
+
+    let parser = Parser::new_ext(raw, options);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    // `pre`/`code` are already in ammonia's default allow-list; the only
+    // thing we need to add is letting `code`'s `class` through so fenced
+    // code blocks keep their `language-…` class for syntax highlighting.
+    let safe_html = ammonia::Builder::default()
+        .add_tag_attributes("code", &["class"])
+        .clean(&unsafe_html)
+        .to_string();
+
+    Html::from_html_unchecked(safe_html.into())
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -23,6 +171,11 @@ pub enum MsgTypes {
     Users,
     Register,
     Message,
+    Presence,
+    Typing,
+    Draw,
+    ClearBoard,
+    DirectMessage,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -31,12 +184,17 @@ struct WebSocketMessage {
     message_type: MsgTypes,
     data_array: Option<Vec<String>>,
     data: Option<String>,
+    /// Recipient username for a `DirectMessage` frame; absent for
+    /// room-wide frames.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    to: Option<String>,
 }
 
 #[derive(Clone)]
 struct UserProfile {
     name: String,
     avatar: String,
+    status: PresenceStatus,
 }
 
 pub struct Chat {
@@ -45,7 +203,158 @@ pub struct Chat {
     _producer: Box<dyn Bridge<EventBus>>,
     wss: WebsocketService,
     messages: Vec<MessageData>,
+    username: String,
+    typing_throttle: Option<Timeout>,
+    typing_clear: Option<Timeout>,
+    canvas_ref: NodeRef,
+    lines: Vec<Stroke>,
+    current_stroke: Option<Stroke>,
+    connection_status: ConnectionStatus,
+    current_room: Room,
+    unread: HashMap<String, usize>,
+    typing_expiry: HashMap<String, Timeout>,
 }
+impl Chat {
+    fn send_typing(&self) {
+        let payload = TypingData {
+            from: self.username.clone(),
+        };
+        let message = WebSocketMessage {
+            message_type: MsgTypes::Typing,
+            data: Some(serde_json::to_string(&payload).unwrap()),
+            data_array: None,
+            to: None,
+        };
+        if let Err(e) = self
+            .wss
+            .tx
+            .clone()
+            .try_send(serde_json::to_string(&message).unwrap())
+        {
+            log::debug!("error sending typing frame: {:?}", e);
+        }
+    }
+
+    fn send_presence(&self, status: PresenceStatus) {
+        let payload = PresenceData {
+            from: self.username.clone(),
+            status,
+        };
+        let message = WebSocketMessage {
+            message_type: MsgTypes::Presence,
+            data: Some(serde_json::to_string(&payload).unwrap()),
+            data_array: None,
+            to: None,
+        };
+        if let Err(e) = self
+            .wss
+            .tx
+            .clone()
+            .try_send(serde_json::to_string(&message).unwrap())
+        {
+            log::debug!("error sending presence frame: {:?}", e);
+        }
+    }
+
+    fn send_stroke(&self, stroke: &Stroke) {
+        let message = WebSocketMessage {
+            message_type: MsgTypes::Draw,
+            data: Some(serde_json::to_string(stroke).unwrap()),
+            data_array: None,
+            to: None,
+        };
+        if let Err(e) = self
+            .wss
+            .tx
+            .clone()
+            .try_send(serde_json::to_string(&message).unwrap())
+        {
+            log::debug!("error sending stroke: {:?}", e);
+        }
+    }
+
+    /// Converts a pointer event's CSS-pixel offset into the canvas's bitmap
+    /// coordinate space. The `<canvas>` has a fixed `width`/`height` but a
+    /// fluid CSS display size, so `offset_x`/`offset_y` (relative to the
+    /// rendered box) must be rescaled before they're usable as drawing
+    /// coordinates, or strokes drift whenever the rendered size isn't
+    /// exactly the bitmap size.
+    fn pointer_canvas_pos(e: &PointerEvent) -> (f64, f64) {
+        let Some(canvas) = e
+            .target()
+            .and_then(|t| t.dyn_into::<HtmlCanvasElement>().ok())
+        else {
+            return (e.offset_x() as f64, e.offset_y() as f64);
+        };
+        let client_width = canvas.client_width() as f64;
+        let client_height = canvas.client_height() as f64;
+        let scale_x = if client_width > 0.0 {
+            canvas.width() as f64 / client_width
+        } else {
+            1.0
+        };
+        let scale_y = if client_height > 0.0 {
+            canvas.height() as f64 / client_height
+        } else {
+            1.0
+        };
+        (e.offset_x() as f64 * scale_x, e.offset_y() as f64 * scale_y)
+    }
+
+    fn canvas_context(&self) -> Option<CanvasRenderingContext2d> {
+        let canvas = self.canvas_ref.cast::<HtmlCanvasElement>()?;
+        canvas
+            .get_context("2d")
+            .ok()??
+            .dyn_into::<CanvasRenderingContext2d>()
+            .ok()
+    }
+
+    fn redraw_canvas(&self) {
+        let (Some(canvas), Some(ctx)) = (
+            self.canvas_ref.cast::<HtmlCanvasElement>(),
+            self.canvas_context(),
+        ) else {
+            return;
+        };
+        ctx.clear_rect(0.0, 0.0, canvas.width() as f64, canvas.height() as f64);
+        for stroke in self.lines.iter().chain(self.current_stroke.iter()) {
+            Self::paint_stroke(&ctx, stroke);
+        }
+    }
+
+    fn paint_stroke(ctx: &CanvasRenderingContext2d, stroke: &Stroke) {
+        if stroke.points.len() < 2 {
+            return;
+        }
+        ctx.set_stroke_style(&stroke.color.clone().into());
+        ctx.set_line_width(stroke.width);
+        ctx.set_line_cap("round");
+        ctx.begin_path();
+        let (x0, y0) = stroke.points[0];
+        ctx.move_to(x0, y0);
+        for &(x, y) in &stroke.points[1..] {
+            ctx.line_to(x, y);
+        }
+        ctx.stroke();
+    }
+
+    /// Messages belonging to the currently open room: everyone's chatter
+    /// for `Room::Public`, or just the two-way thread with `peer` for a DM.
+    fn visible_messages(&self) -> Vec<&MessageData> {
+        self.messages
+            .iter()
+            .filter(|m| match &self.current_room {
+                Room::Public => m.to.is_none(),
+                Room::Direct(peer) => {
+                    (m.is_mine() && m.to.as_deref() == Some(peer.as_str()))
+                        || (!m.is_mine() && m.from == *peer && m.to.as_deref() == Some(self.username.as_str()))
+                }
+            })
+            .collect()
+    }
+}
+
 impl Component for Chat {
     type Message = Msg;
     type Properties = ();
@@ -55,33 +364,41 @@ impl Component for Chat {
             .link()
             .context::<User>(Callback::noop())
             .expect("context to be set");
-        let wss = WebsocketService::new();
         let username = user.username.borrow().clone();
 
-        let message = WebSocketMessage {
+        let register_message = WebSocketMessage {
             message_type: MsgTypes::Register,
             data: Some(username.to_string()),
             data_array: None,
+            to: None,
         };
+        let register_frame = serde_json::to_string(&register_message).unwrap();
 
-        if let Ok(_) = wss
-            .tx
-            .clone()
-            .try_send(serde_json::to_string(&message).unwrap())
-        {
-            log::debug!("message sent successfully");
-        }
+        let wss = WebsocketService::new(
+            register_frame,
+            ctx.link().callback(Msg::ConnectionStatus),
+        );
 
         Self {
             users: vec![],
             messages: vec![],
             chat_input: NodeRef::default(),
             wss,
+            username,
+            typing_throttle: None,
+            typing_clear: None,
+            canvas_ref: NodeRef::default(),
+            lines: vec![],
+            current_stroke: None,
+            connection_status: ConnectionStatus::Reconnecting,
+            current_room: Room::Public,
+            unread: HashMap::new(),
+            typing_expiry: HashMap::new(),
             _producer: EventBus::bridge(ctx.link().callback(Msg::HandleMsg)),
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::HandleMsg(s) => {
                 let msg: WebSocketMessage = serde_json::from_str(&s).unwrap();
@@ -97,14 +414,99 @@ impl Component for Chat {
                                     u
                                 )
                                 .into(),
+                                status: PresenceStatus::Online,
                             })
                             .collect();
                         return true;
                     }
                     MsgTypes::Message => {
-                        let message_data: MessageData =
+                        let mut message_data: MessageData =
                             serde_json::from_str(&msg.data.unwrap()).unwrap();
+                        if message_data.sent_at.is_none() {
+                            message_data.sent_at = Some(js_sys::Date::now());
+                        }
                         self.messages.push(message_data);
+                        self.messages.sort_by(|a, b| {
+                            a.sent_at
+                                .partial_cmp(&b.sent_at)
+                                .unwrap_or(std::cmp::Ordering::Equal)
+                        });
+                        return true;
+                    }
+                    MsgTypes::Presence => {
+                        let Ok(presence) =
+                            serde_json::from_str::<PresenceData>(&msg.data.unwrap_or_default())
+                        else {
+                            return false;
+                        };
+                        self.typing_expiry.remove(&presence.from);
+                        if let Some(u) = self.users.iter_mut().find(|u| u.name == presence.from) {
+                            u.status = presence.status;
+                        }
+                        return true;
+                    }
+                    MsgTypes::Typing => {
+                        let Ok(typing) =
+                            serde_json::from_str::<TypingData>(&msg.data.unwrap_or_default())
+                        else {
+                            return false;
+                        };
+                        if let Some(u) = self.users.iter_mut().find(|u| u.name == typing.from) {
+                            u.status = PresenceStatus::Typing;
+                        }
+                        let from = typing.from;
+                        let link = ctx.link().clone();
+                        self.typing_expiry.insert(
+                            from.clone(),
+                            Timeout::new(TYPING_RECEIVER_EXPIRE_MS, move || {
+                                link.send_message(Msg::TypingExpired(from));
+                            }),
+                        );
+                        return true;
+                    }
+                    MsgTypes::Draw => {
+                        if let Some(data) = msg.data {
+                            if let Ok(stroke) = serde_json::from_str::<Stroke>(&data) {
+                                self.lines.push(stroke);
+                            }
+                        }
+                        return true;
+                    }
+                    MsgTypes::ClearBoard => {
+                        self.lines.clear();
+                        return true;
+                    }
+                    MsgTypes::DirectMessage => {
+                        let Ok(mut message_data) =
+                            serde_json::from_str::<MessageData>(&msg.data.unwrap_or_default())
+                        else {
+                            return false;
+                        };
+                        // The broadcast socket has no server-side scoping, so
+                        // every client receives every `DirectMessage` frame.
+                        // Drop anything not addressed to (or sent by) us
+                        // instead of storing it — a modified client must not
+                        // be able to read other users' threads out of state.
+                        let is_mine = message_data.is_mine();
+                        if message_data.to.as_deref() != Some(self.username.as_str()) && !is_mine {
+                            return false;
+                        }
+                        if message_data.sent_at.is_none() {
+                            message_data.sent_at = Some(js_sys::Date::now());
+                        }
+                        let is_focused = matches!(
+                            &self.current_room,
+                            Room::Direct(peer) if *peer == message_data.from
+                        );
+                        if !is_mine && !is_focused {
+                            *self.unread.entry(message_data.from.clone()).or_insert(0) += 1;
+                        }
+                        self.messages.push(message_data);
+                        self.messages.sort_by(|a, b| {
+                            a.sent_at
+                                .partial_cmp(&b.sent_at)
+                                .unwrap_or(std::cmp::Ordering::Equal)
+                        });
                         return true;
                     }
                     _ => {
@@ -115,10 +517,15 @@ impl Component for Chat {
             Msg::SubmitMessage => {
                 let input = self.chat_input.cast::<HtmlInputElement>();
                 if let Some(input) = input {
+                    let (message_type, to) = match &self.current_room {
+                        Room::Public => (MsgTypes::Message, None),
+                        Room::Direct(peer) => (MsgTypes::DirectMessage, Some(peer.clone())),
+                    };
                     let message = WebSocketMessage {
-                        message_type: MsgTypes::Message,
+                        message_type,
                         data: Some(input.value()),
                         data_array: None,
+                        to,
                     };
                     if let Err(e) = self
                         .wss
@@ -130,11 +537,104 @@ impl Component for Chat {
                     }
                     input.set_value("");
                 };
+                self.typing_throttle = None;
+                self.typing_clear = None;
+                self.send_presence(PresenceStatus::Online);
+                false
+            }
+            Msg::InputChanged => {
+                if self.typing_throttle.is_none() {
+                    self.send_typing();
+                    let link = ctx.link().clone();
+                    self.typing_throttle = Some(Timeout::new(TYPING_THROTTLE_MS, move || {
+                        link.send_message(Msg::TypingThrottleElapsed);
+                    }));
+                }
+                let link = ctx.link().clone();
+                self.typing_clear = Some(Timeout::new(TYPING_CLEAR_MS, move || {
+                    link.send_message(Msg::StopTyping);
+                }));
+                false
+            }
+            Msg::TypingThrottleElapsed => {
+                self.typing_throttle = None;
+                false
+            }
+            Msg::StopTyping => {
+                self.typing_clear = None;
+                self.send_presence(PresenceStatus::Online);
+                false
+            }
+            Msg::TypingExpired(from) => {
+                self.typing_expiry.remove(&from);
+                if let Some(u) = self.users.iter_mut().find(|u| u.name == from) {
+                    if u.status == PresenceStatus::Typing {
+                        u.status = PresenceStatus::Online;
+                    }
+                }
+                true
+            }
+            Msg::PointerDown(x, y) => {
+                self.current_stroke = Some(Stroke {
+                    color: "#1f2937".to_string(),
+                    width: 3.0,
+                    points: vec![(x, y)],
+                });
+                false
+            }
+            Msg::PointerMove(x, y) => {
+                if let Some(stroke) = self.current_stroke.as_mut() {
+                    stroke.points.push((x, y));
+                    self.redraw_canvas();
+                }
+                false
+            }
+            Msg::PointerUp => {
+                if let Some(stroke) = self.current_stroke.take() {
+                    if stroke.points.len() > 1 {
+                        self.send_stroke(&stroke);
+                        self.lines.push(stroke);
+                    }
+                }
+                false
+            }
+            Msg::ClearBoard => {
+                self.lines.clear();
+                self.redraw_canvas();
+                let message = WebSocketMessage {
+                    message_type: MsgTypes::ClearBoard,
+                    data: None,
+                    data_array: None,
+                    to: None,
+                };
+                if let Err(e) = self
+                    .wss
+                    .tx
+                    .clone()
+                    .try_send(serde_json::to_string(&message).unwrap())
+                {
+                    log::debug!("error sending clear-board frame: {:?}", e);
+                }
                 false
             }
+            Msg::ConnectionStatus(status) => {
+                self.connection_status = status;
+                true
+            }
+            Msg::OpenRoom(room) => {
+                if let Room::Direct(peer) = &room {
+                    self.unread.remove(peer);
+                }
+                self.current_room = room;
+                true
+            }
         }
     }
 
+    fn rendered(&mut self, _ctx: &Context<Self>, _first_render: bool) {
+        self.redraw_canvas();
+    }
+
     fn view(&self, ctx: &Context<Self>) -> Html {
         let submit = ctx.link().callback(|_| Msg::SubmitMessage);
         let submit_on_enter = ctx.link().batch_callback(|e: KeyboardEvent| {
@@ -146,6 +646,15 @@ impl Component for Chat {
         });
 
         html! {
+            <>
+            <style>
+                {".prose pre { background: #1e293b; color: #e2e8f0; padding: 0.75rem 1rem; border-radius: 0.5rem; overflow-x: auto; }
+                .prose code { font-family: ui-monospace, SFMono-Regular, Menlo, monospace; font-size: 0.85em; }
+                .prose pre code { background: none; padding: 0; }
+                .prose :not(pre) > code { background: rgba(0, 0, 0, 0.06); padding: 0.1em 0.3em; border-radius: 0.25rem; }
+                .prose ul, .prose ol { padding-left: 1.25rem; }
+                .prose a { text-decoration: underline; }"}
+            </style>
             <div class="flex w-screen h-screen bg-gray-50">
                 <div class="flex-none w-64 h-screen bg-white shadow-md">
                     <div class="p-4 border-b border-gray-200">
@@ -157,6 +666,18 @@ impl Component for Chat {
                         </div>
                     </div>
                     <div class="overflow-y-auto">
+                        <div
+                            onclick={ctx.link().callback(|_| Msg::OpenRoom(Room::Public))}
+                            class={format!("flex items-center p-3 cursor-pointer hover:bg-gray-50 {}",
+                                if self.current_room == Room::Public { "bg-blue-50" } else { "" })}
+                        >
+                            <div class="flex items-center justify-center w-10 h-10 bg-blue-500 rounded-full text-white mr-3">
+                                <svg xmlns="http://www.w3.org/2000/svg" class="h-5 w-5" fill="none" viewBox="0 0 24 24" stroke="currentColor">
+                                    <path stroke-linecap="round" stroke-linejoin="round" stroke-width="2" d="M17 20h5v-2a4 4 0 00-3-3.87M9 20H4v-2a4 4 0 013-3.87m6-4.13a4 4 0 10-4-4 4 4 0 004 4zm6 0a4 4 0 10-4-4" />
+                                </svg>
+                            </div>
+                            <div class="text-sm font-medium">{"Public Room"}</div>
+                        </div>
                         <div class="p-3 text-xs font-bold text-gray-500 uppercase">{"Active Users"}</div>
                         {
                             if self.users.is_empty() {
@@ -175,16 +696,37 @@ impl Component for Chat {
                                     <>
                                     {
                                         self.users.clone().iter().map(|u| {
+                                            let dot_class = match u.status {
+                                                PresenceStatus::Online => "bg-green-500",
+                                                PresenceStatus::Away => "bg-gray-400",
+                                                PresenceStatus::Typing => "bg-blue-500",
+                                            };
+                                            let status_label = match u.status {
+                                                PresenceStatus::Online => "Online".to_string(),
+                                                PresenceStatus::Away => "Away".to_string(),
+                                                PresenceStatus::Typing => "typing…".to_string(),
+                                            };
+                                            let is_active = self.current_room == Room::Direct(u.name.clone());
+                                            let unread_count = self.unread.get(&u.name).copied().unwrap_or(0);
+                                            let name_for_click = u.name.clone();
                                             html!{
-                                                <div class="flex items-center p-3 hover:bg-gray-50 cursor-pointer">
+                                                <div
+                                                    onclick={ctx.link().callback(move |_| Msg::OpenRoom(Room::Direct(name_for_click.clone())))}
+                                                    class={format!("flex items-center p-3 hover:bg-gray-50 cursor-pointer {}", if is_active { "bg-blue-50" } else { "" })}
+                                                >
                                                     <div class="relative">
                                                         <img class="w-12 h-12 rounded-full shadow-sm" src={u.avatar.clone()} alt="avatar"/>
-                                                        <div class="absolute bottom-0 right-0 w-3 h-3 bg-green-500 rounded-full border-2 border-white"></div>
+                                                        <div class={format!("absolute bottom-0 right-0 w-3 h-3 {} rounded-full border-2 border-white", dot_class)}></div>
                                                     </div>
-                                                    <div class="ml-3">
+                                                    <div class="ml-3 flex-1">
                                                         <div class="text-sm font-medium">{u.name.clone()}</div>
-                                                        <div class="text-xs text-gray-500">{"Online"}</div>
+                                                        <div class="text-xs text-gray-500">{status_label}</div>
                                                     </div>
+                                                    if unread_count > 0 {
+                                                        <div class="ml-2 min-w-[1.25rem] h-5 px-1 flex items-center justify-center text-xs font-bold text-white bg-red-500 rounded-full">
+                                                            {unread_count}
+                                                        </div>
+                                                    }
                                                 </div>
                                             }
                                         }).collect::<Html>()
@@ -204,14 +746,34 @@ impl Component for Chat {
                                 </svg>
                             </div>
                             <div class="ml-3">
-                                <div class="text-lg font-medium">{"Chat Room"}</div>
-                                <div class="text-xs text-gray-500">{format!("{} participants", self.users.len())}</div>
+                                {
+                                    match &self.current_room {
+                                        Room::Public => html! {
+                                            <>
+                                            <div class="text-lg font-medium">{"Chat Room"}</div>
+                                            <div class="text-xs text-gray-500">{format!("{} participants", self.users.len())}</div>
+                                            </>
+                                        },
+                                        Room::Direct(peer) => html! {
+                                            <>
+                                            <div class="text-lg font-medium">{format!("Direct message: {}", peer)}</div>
+                                            <div class="text-xs text-gray-500">{"Only visible to you and them"}</div>
+                                            </>
+                                        },
+                                    }
+                                }
                             </div>
                         </div>
                     </div>
+                    if self.connection_status == ConnectionStatus::Reconnecting {
+                        <div class="w-full px-4 py-2 bg-amber-100 text-amber-800 text-sm text-center">
+                            {"Reconnecting…"}
+                        </div>
+                    }
                     <div class="w-full grow overflow-auto p-4 bg-gray-50">
                         {
-                            if self.messages.is_empty() {
+                            let visible_messages = self.visible_messages();
+                            if visible_messages.is_empty() {
                                 html! {
                                     <div class="flex flex-col items-center justify-center h-full text-gray-500">
                                         <svg xmlns="http://www.w3.org/2000/svg" class="h-16 w-16 mb-4 text-gray-300" fill="none" viewBox="0 0 24 24" stroke="currentColor">
@@ -224,16 +786,28 @@ impl Component for Chat {
                                 html! {
                                     <>
                                     {
-                                        self.messages.iter().map(|m| {
-                                            let is_current_user = m.from == "You";
-                                            
+                                        visible_messages.iter().enumerate().map(|(i, m)| {
+                                            let is_current_user = m.is_mine();
+                                            let sent_at = m.sent_at.unwrap_or_else(js_sys::Date::now);
+
                                             let avatar_url = if let Some(user) = self.users.iter().find(|u| u.name == m.from) {
                                                 user.avatar.clone()
                                             } else {
                                                 format!("https://avatars.dicebear.com/api/adventurer-neutral/{}.svg", m.from)
                                             };
-                                            
+
+                                            let show_date_separator = match i.checked_sub(1).and_then(|p| visible_messages.get(p)) {
+                                                Some(prev) => day_label(prev.sent_at.unwrap_or_else(js_sys::Date::now)) != day_label(sent_at),
+                                                None => true,
+                                            };
+
                                             html!{
+                                                <>
+                                                if show_date_separator {
+                                                    <div class="flex justify-center my-3">
+                                                        <span class="text-xs text-gray-400 bg-gray-100 px-3 py-1 rounded-full">{day_label(sent_at)}</span>
+                                                    </div>
+                                                }
                                                 <div class={format!("flex mb-4 {}", if is_current_user { "justify-end" } else { "" })}>
                                                     {
                                                         if !is_current_user {
@@ -261,15 +835,15 @@ impl Component for Chat {
                                                                 html! {}
                                                             }
                                                         }
-                                                        <div class={format!("text-sm {}", if is_current_user { "text-white" } else { "text-gray-800" })}>
+                                                        <div class={format!("text-sm prose prose-sm max-w-none {}", if is_current_user { "text-white" } else { "text-gray-800" })}>
                                                             if m.message.ends_with(".gif") || m.message.contains("giphy.com") {
                                                                 <img class="mt-2 rounded-lg max-w-full" src={m.message.clone()} alt="gif"/>
                                                             } else {
-                                                                {m.message.clone()}
+                                                                { render_message_html(&m.message) }
                                                             }
                                                         </div>
                                                         <div class={format!("text-xs mt-1 text-right {}", if is_current_user { "text-blue-100" } else { "text-gray-400" })}>
-                                                            {"now"}
+                                                            {relative_time(sent_at)}
                                                         </div>
                                                     </div>
                                                     {
@@ -284,6 +858,7 @@ impl Component for Chat {
                                                         }
                                                     }
                                                 </div>
+                                                </>
                                             }
                                         }).collect::<Html>()
                                     }
@@ -292,18 +867,48 @@ impl Component for Chat {
                             }
                         }
                     </div>
+                    <div class="w-full p-3 bg-white border-t border-gray-200">
+                        <div class="flex items-center justify-between mb-2">
+                            <div class="text-xs font-bold text-gray-500 uppercase">{"Whiteboard"}</div>
+                            <button
+                                onclick={ctx.link().callback(|_| Msg::ClearBoard)}
+                                class="text-xs text-blue-500 hover:underline focus:outline-none"
+                            >
+                                {"Clear"}
+                            </button>
+                        </div>
+                        <canvas
+                            ref={self.canvas_ref.clone()}
+                            width="760"
+                            height="180"
+                            class="w-full border border-gray-200 rounded-lg bg-gray-50"
+                            style="touch-action: none;"
+                            onpointerdown={ctx.link().callback(|e: PointerEvent| {
+                                let (x, y) = Self::pointer_canvas_pos(&e);
+                                Msg::PointerDown(x, y)
+                            })}
+                            onpointermove={ctx.link().callback(|e: PointerEvent| {
+                                let (x, y) = Self::pointer_canvas_pos(&e);
+                                Msg::PointerMove(x, y)
+                            })}
+                            onpointerup={ctx.link().callback(|_: PointerEvent| Msg::PointerUp)}
+                            onpointerleave={ctx.link().callback(|_: PointerEvent| Msg::PointerUp)}
+                        ></canvas>
+                    </div>
                     <div class="w-full p-4 bg-white border-t border-gray-200">
                         <div class="flex">
-                            <input 
-                                ref={self.chat_input.clone()} 
-                                type="text" 
-                                placeholder="Type a message..." 
+                            <input
+                                ref={self.chat_input.clone()}
+                                type="text"
+                                placeholder="Type a message..."
                                 class="flex-1 py-2 px-4 bg-gray-100 rounded-l-full outline-none focus:ring-2 focus:ring-blue-400 focus:bg-white"
                                 onkeyup={submit_on_enter}
+                                oninput={ctx.link().callback(|_: InputEvent| Msg::InputChanged)}
                             />
-                            <button 
-                                onclick={submit} 
-                                class="px-6 py-2 bg-blue-500 hover:bg-blue-600 text-white rounded-r-full transition-colors focus:outline-none"
+                            <button
+                                onclick={submit}
+                                disabled={self.connection_status == ConnectionStatus::Reconnecting}
+                                class="px-6 py-2 bg-blue-500 hover:bg-blue-600 disabled:bg-gray-300 disabled:cursor-not-allowed text-white rounded-r-full transition-colors focus:outline-none"
                             >
                                 <div class="flex items-center">
                                     <span class="mr-2 hidden sm:inline">{"Send"}</span>
@@ -316,6 +921,7 @@ impl Component for Chat {
                     </div>
                 </div>
             </div>
+            </>
         }
     }
 }
\ No newline at end of file
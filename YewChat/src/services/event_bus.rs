@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+
+use yew_agent::{Agent, AgentLink, Context, HandlerId, Public};
+
+/// Fans out raw WebSocket frames (as JSON strings) from `WebsocketService`
+/// to every `Chat` component bridged to it.
+pub struct EventBus {
+    link: AgentLink<EventBus>,
+    subscribers: HashSet<HandlerId>,
+}
+
+impl Agent for EventBus {
+    type Reach = Context<Self>;
+    type Message = ();
+    type Input = String;
+    type Output = String;
+
+    fn create(link: AgentLink<Self>) -> Self {
+        Self {
+            link,
+            subscribers: HashSet::new(),
+        }
+    }
+
+    fn update(&mut self, _msg: Self::Message) {}
+
+    fn handle_input(&mut self, msg: Self::Input, _id: HandlerId) {
+        for sub in self.subscribers.iter() {
+            self.link.respond(*sub, msg.clone());
+        }
+    }
+
+    fn connected(&mut self, id: HandlerId) {
+        self.subscribers.insert(id);
+    }
+
+    fn disconnected(&mut self, id: HandlerId) {
+        self.subscribers.remove(&id);
+    }
+}
@@ -0,0 +1,125 @@
+use futures::{channel::mpsc::Receiver, channel::mpsc::Sender, SinkExt, StreamExt};
+use gloo_net::websocket::{futures::WebSocket, Message, State};
+use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen_futures::spawn_local;
+use yew::Callback;
+use yew_agent::{Dispatched, Dispatcher};
+
+use super::event_bus::EventBus;
+
+const WS_URL: &str = "ws://127.0.0.1:8080/ws";
+const INITIAL_BACKOFF_MS: u32 = 1_000;
+const MAX_BACKOFF_MS: u32 = 16_000;
+
+/// Connection state surfaced to `Chat` so the `view` can show a banner and
+/// disable the send button while the socket is down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connected,
+    Reconnecting,
+}
+
+pub struct WebsocketService {
+    pub tx: Sender<String>,
+}
+
+impl WebsocketService {
+    /// Opens the socket and keeps it open, reconnecting with exponential
+    /// backoff if it drops. `register_frame` is re-sent on every successful
+    /// (re)connect so the user reappears in the roster. Sends attempted
+    /// while disconnected sit in the channel's own buffer and are flushed,
+    /// in order, once the next connection is established.
+    pub fn new(register_frame: String, on_status: Callback<ConnectionStatus>) -> Self {
+        let (out_tx, out_rx) = futures::channel::mpsc::channel::<String>(1000);
+        spawn_local(connect_loop(register_frame, on_status, out_rx));
+        Self { tx: out_tx }
+    }
+}
+
+/// Polls the raw socket's `readyState` until the handshake finishes.
+/// `WebSocket::open` only constructs the JS socket and returns immediately;
+/// it does not wait for the connection to actually come up, so callers must
+/// confirm `State::Open` themselves before treating the socket as usable.
+async fn wait_until_open(ws: &WebSocket) -> bool {
+    loop {
+        match ws.state() {
+            State::Open => return true,
+            State::Closing | State::Closed => return false,
+            State::Connecting => TimeoutFuture::new(20).await,
+        }
+    }
+}
+
+async fn connect_loop(
+    register_frame: String,
+    on_status: Callback<ConnectionStatus>,
+    mut out_rx: Receiver<String>,
+) {
+    let mut backoff_ms = INITIAL_BACKOFF_MS;
+
+    loop {
+        match WebSocket::open(WS_URL) {
+            Ok(ws) => {
+                if !wait_until_open(&ws).await {
+                    log::debug!("socket closed before the handshake completed");
+                    on_status.emit(ConnectionStatus::Reconnecting);
+                    TimeoutFuture::new(backoff_ms).await;
+                    backoff_ms = (backoff_ms * 2).min(MAX_BACKOFF_MS);
+                    continue;
+                }
+
+                log::debug!("connected to websocket");
+                backoff_ms = INITIAL_BACKOFF_MS;
+                on_status.emit(ConnectionStatus::Connected);
+
+                let (mut write, mut read) = ws.split();
+
+                if let Err(e) = write.send(Message::Text(register_frame.clone())).await {
+                    log::debug!("failed to send register frame: {:?}", e);
+                }
+
+                let outbound = async {
+                    while let Some(msg) = out_rx.next().await {
+                        if let Err(e) = write.send(Message::Text(msg)).await {
+                            log::debug!("send failed, connection likely dropped: {:?}", e);
+                            break;
+                        }
+                    }
+                };
+
+                let inbound = async {
+                    while let Some(msg) = read.next().await {
+                        match msg {
+                            Ok(Message::Text(data)) => {
+                                EventBus::dispatcher().send(data);
+                            }
+                            Ok(Message::Bytes(b)) => {
+                                if let Ok(val) = std::str::from_utf8(&b) {
+                                    EventBus::dispatcher().send(val.to_string());
+                                }
+                            }
+                            Err(e) => {
+                                log::debug!("websocket read error: {:?}", e);
+                                break;
+                            }
+                        }
+                    }
+                };
+
+                futures::pin_mut!(outbound);
+                futures::pin_mut!(inbound);
+                futures::future::select(outbound, inbound).await;
+
+                log::debug!("websocket connection lost, will reconnect");
+                on_status.emit(ConnectionStatus::Reconnecting);
+            }
+            Err(e) => {
+                log::debug!("failed to open websocket: {:?}", e);
+                on_status.emit(ConnectionStatus::Reconnecting);
+            }
+        }
+
+        TimeoutFuture::new(backoff_ms).await;
+        backoff_ms = (backoff_ms * 2).min(MAX_BACKOFF_MS);
+    }
+}